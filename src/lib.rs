@@ -1,56 +1,207 @@
 #![feature(unboxed_closures)]
 #![allow(unstable, unused)]
 
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::marker::PhantomData;
 use std::ops::Fn;
 
 #[macro_use]
 mod macros;
 
-type ParseResult<G, O> = Result<(G, O), ()>;
+#[derive(Clone, Debug, PartialEq)]
+struct ParseError {
+    offset: usize,
+    expected: Vec<Cow<'static, str>>
+}
+
+impl ParseError {
+    fn new(offset: usize, expected: Cow<'static, str>) -> ParseError {
+        ParseError { offset: offset, expected: vec![expected] }
+    }
+
+    fn merge(self, other: ParseError) -> ParseError {
+        if self.offset > other.offset {
+            self
+        } else if other.offset > self.offset {
+            other
+        } else {
+            let mut expected = self.expected;
+            expected.extend(other.expected);
+            ParseError { offset: self.offset, expected: expected }
+        }
+    }
+}
+
+type ParseResult<G, O> = Result<(G, O), ParseError>;
 
 trait Parser<I, O> {
     fn parse(&self, I) -> ParseResult<I, O>;
 }
 
-trait Generator<I: ?Sized>: Clone {
+#[derive(Clone, Debug, PartialEq)]
+enum Representation {
+    Terminal(String),
+    Nonterminal(String),
+    Sequence(Vec<Representation>),
+    Choice(Vec<Representation>),
+    Repeat(Box<Representation>),
+    Optional(Box<Representation>)
+}
+
+trait Representable {
+    fn representation(&self) -> Representation;
+
+    fn to_ebnf(&self) -> String {
+        render_ebnf(&self.representation())
+    }
+}
+
+fn render_ebnf(repr: &Representation) -> String {
+    match *repr {
+        Representation::Terminal(ref s) => s.clone(),
+        Representation::Nonterminal(ref s) => s.clone(),
+        Representation::Sequence(ref reprs) => {
+            reprs.iter().map(render_ebnf).collect::<Vec<_>>().connect(", ")
+        }
+        Representation::Choice(ref reprs) => {
+            reprs.iter().map(render_ebnf).collect::<Vec<_>>().connect(" | ")
+        }
+        Representation::Repeat(ref r) => format!("{{ {} }}", render_ebnf(r)),
+        Representation::Optional(ref r) => format!("[ {} ]", render_ebnf(r))
+    }
+}
+
+trait Generator: Clone {
+    type Content: ?Sized;
+
     fn forward(&self, offset: usize) -> Self;
-    fn get(&self) -> &I;
+    fn get(&self) -> &Self::Content;
+    fn position(&self) -> usize;
 }
 
 #[derive(Clone)]
 struct StrGenerator<'a> {
-    st: &'a str
+    st: &'a str,
+    orig_len: usize
 }
 
-impl <'a> Generator<str> for StrGenerator<'a> {
+impl <'a> StrGenerator<'a> {
+    fn new(st: &'a str) -> StrGenerator<'a> {
+        StrGenerator { st: st, orig_len: st.len() }
+    }
+}
+
+impl <'a> Generator for StrGenerator<'a> {
+    type Content = str;
+
     fn forward(&self, offset: usize) -> StrGenerator<'a> {
-        StrGenerator {st: &self.st[offset..]}
+        StrGenerator { st: &self.st[offset..], orig_len: self.orig_len }
     }
 
     fn get(&self) -> &str {
         self.st
     }
+
+    fn position(&self) -> usize {
+        self.orig_len - self.st.len()
+    }
 }
 
-impl <I: Generator<str>> Parser<I, char> for char {
+impl <I: Generator<Content = str>> Parser<I, char> for char {
     fn parse(&self, input: I) -> ParseResult<I, char> {
         match input.get().chars().nth(0) {
             Some(c) if c == *self => {
                 let size = c.len_utf8();
                 Ok((input.forward(size), c))
             }
-            _ => Err(())
+            _ => Err(ParseError::new(input.position(), Cow::Owned(format!("'{}'", self))))
         }
     }
 }
 
-impl <'a, I: Generator<str>> Parser<I, String> for &'a str {
+impl Representable for char {
+    fn representation(&self) -> Representation {
+        Representation::Terminal(format!("'{}'", self))
+    }
+}
+
+impl <'a, I: Generator<Content = str>> Parser<I, String> for &'a str {
     fn parse(&self, input: I) -> ParseResult<I, String> {
         if input.get().starts_with(*self) {
             let size = self.len();
             Ok((input.forward(size), input.get().slice_to(size).to_string()))
         } else {
-            Err(())
+            Err(ParseError::new(input.position(), Cow::Owned(format!("\"{}\"", self))))
+        }
+    }
+}
+
+impl <'a> Representable for &'a str {
+    fn representation(&self) -> Representation {
+        Representation::Terminal(format!("\"{}\"", self))
+    }
+}
+
+struct SliceGenerator<'a, T: 'a> {
+    sl: &'a [T],
+    orig_len: usize
+}
+
+impl <'a, T: 'a> SliceGenerator<'a, T> {
+    fn new(sl: &'a [T]) -> SliceGenerator<'a, T> {
+        SliceGenerator { sl: sl, orig_len: sl.len() }
+    }
+}
+
+impl <'a, T: 'a> Clone for SliceGenerator<'a, T> {
+    fn clone(&self) -> SliceGenerator<'a, T> {
+        SliceGenerator { sl: self.sl, orig_len: self.orig_len }
+    }
+}
+
+impl <'a, T: 'a> Generator for SliceGenerator<'a, T> {
+    type Content = [T];
+
+    fn forward(&self, offset: usize) -> SliceGenerator<'a, T> {
+        SliceGenerator { sl: &self.sl[offset..], orig_len: self.orig_len }
+    }
+
+    fn get(&self) -> &[T] {
+        self.sl
+    }
+
+    fn position(&self) -> usize {
+        self.orig_len - self.sl.len()
+    }
+}
+
+struct TokenParser<T> {
+    t: T
+}
+
+fn token<T>(t: T) -> TokenParser<T> {
+    TokenParser { t: t }
+}
+
+impl <I: Generator<Content = [T]>, T: PartialEq + Clone + Debug> Parser<I, T> for TokenParser<T> {
+    fn parse(&self, input: I) -> ParseResult<I, T> {
+        match input.get().first() {
+            Some(t) if *t == self.t => Ok((input.forward(1), t.clone())),
+            _ => Err(ParseError::new(input.position(), Cow::Owned(format!("{:?}", self.t))))
+        }
+    }
+}
+
+impl <'a, I: Generator<Content = [T]>, T: PartialEq + Clone + Debug> Parser<I, Vec<T>> for &'a [T] {
+    fn parse(&self, input: I) -> ParseResult<I, Vec<T>> {
+        let size = self.len();
+        let got = input.get();
+
+        if got.len() >= size && &got[..size] == *self {
+            Ok((input.forward(size), self.to_vec()))
+        } else {
+            Err(ParseError::new(input.position(), Cow::Owned(format!("{:?}", self))))
         }
     }
 }
@@ -69,6 +220,12 @@ impl <I, Ao, Bo, L: Parser<I, Ao>, R: Parser<I, Bo>> Parser<I, (Ao, Bo)> for Con
     }
 }
 
+impl <L: Representable, R: Representable> Representable for ConcatParser<L, R> {
+    fn representation(&self) -> Representation {
+        Representation::Sequence(vec![self.l.representation(), self.r.representation()])
+    }
+}
+
 
 #[derive(Clone, Copy)]
 struct MaybeParser<P> {
@@ -79,46 +236,124 @@ impl <I, O, P: Parser<I, O>> Parser<I, Option<O>> for MaybeParser<P> {
     fn parse(&self, input: I) -> ParseResult<I, Option<O>> {
         match self.p.parse(input) {
             Ok((i, r)) => Ok((i, Some(r))),
-            Err(())  => Err(())
+            Err(e) => Err(e)
         }
     }
 }
 
+impl <P: Representable> Representable for MaybeParser<P> {
+    fn representation(&self) -> Representation {
+        Representation::Optional(Box::new(self.p.representation()))
+    }
+}
+
 #[derive(Clone, Copy)]
 struct RepeatParser<P> {
     p: P,
-    limit: Option<usize>
+    min: usize,
+    max: Option<usize>
 }
 
-impl <I: Clone, O, P: Parser<I, O>> Parser<I, Vec<O>> for RepeatParser<P> {
+impl <I: Generator, O, P: Parser<I, O>> Parser<I, Vec<O>> for RepeatParser<P> {
     fn parse(&self, input: I) -> ParseResult<I, Vec<O>> {
         let mut vec = vec![];
         let mut pos = input.clone();
+        let mut last_err = None;
 
         loop {
+            if let Some(max) = self.max {
+                if vec.len() >= max {
+                    break;
+                }
+            }
+
             match self.p.parse(pos.clone()) {
                 Ok((p, r)) => {
                     pos = p;
                     vec.push(r)
                 }
-                Err(()) => {
+                Err(e) => {
+                    last_err = Some(e);
                     break;
                 }
             }
         }
 
-        if let Some(limit) = self.limit {
-            if vec.len() >= limit {
-                Ok((pos, vec))
-            } else {
-                Err(())
-            }
-        } else {
+        if vec.len() >= self.min {
             Ok((pos, vec))
+        } else {
+            Err(last_err.unwrap_or_else(|| {
+                ParseError::new(pos.position(), Cow::Borrowed("repeat: max reached before min"))
+            }))
         }
     }
 }
 
+impl <P: Representable> Representable for RepeatParser<P> {
+    fn representation(&self) -> Representation {
+        Representation::Repeat(Box::new(self.p.representation()))
+    }
+}
+
+struct SepBy<Item, Sep, S> {
+    item: Item,
+    sep: Sep,
+    trailing: bool,
+    _sep_output: PhantomData<S>
+}
+
+fn sep_by<Item, Sep, S>(item: Item, sep: Sep) -> SepBy<Item, Sep, S> {
+    SepBy { item: item, sep: sep, trailing: false, _sep_output: PhantomData }
+}
+
+impl <Item, Sep, S> SepBy<Item, Sep, S> {
+    fn allow_trailing(mut self, allow: bool) -> SepBy<Item, Sep, S> {
+        self.trailing = allow;
+        self
+    }
+}
+
+impl <I: Clone, O, S, Item: Parser<I, O>, Sep: Parser<I, S>> Parser<I, Vec<O>> for SepBy<Item, Sep, S> {
+    fn parse(&self, input: I) -> ParseResult<I, Vec<O>> {
+        let (mut pos, first) = match self.item.parse(input.clone()) {
+            Ok(r) => r,
+            Err(_) => return Ok((input, vec![]))
+        };
+        let mut vec = vec![first];
+
+        loop {
+            match self.sep.parse(pos.clone()) {
+                Ok((after_sep, _)) => {
+                    match self.item.parse(after_sep.clone()) {
+                        Ok((after_item, item)) => {
+                            pos = after_item;
+                            vec.push(item);
+                        }
+                        Err(_) => {
+                            if self.trailing {
+                                pos = after_sep;
+                            }
+                            break;
+                        }
+                    }
+                }
+                Err(_) => break
+            }
+        }
+
+        Ok((pos, vec))
+    }
+}
+
+fn surrounded_by<I, Lo, O, Ro, L, Item, R>(open: L, inner: Item, close: R)
+    -> IgnoreRightParser<IgnoreLeftParser<L, Item, Lo>, R, Ro>
+where L: Parser<I, Lo>, Item: Parser<I, O>, R: Parser<I, Ro> {
+    IgnoreRightParser {
+        l: IgnoreLeftParser { l: open, r: inner },
+        r: close
+    }
+}
+
 #[derive(Clone, Copy)]
 struct MapParser<'a, F: 'a, P, O> {
     p: P,
@@ -129,7 +364,25 @@ impl <'a, I, O, B, P: Parser<I, O>, F: Fn(O) -> B + 'a> Parser<I, B> for MapPars
     fn parse(&self, input: I) -> ParseResult<I, B> {
         match self.p.parse(input) {
             Ok((p, r)) => Ok((p, (self.f)(r))),
-            Err(()) => Err(())
+            Err(e) => Err(e)
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct MapResParser<'a, F: 'a, P, O> {
+    p: P,
+    f: &'a F
+}
+
+impl <'a, I: Generator, O, B, E, P, F> Parser<I, B> for MapResParser<'a, F, P, O>
+where P: Parser<I, O>, F: Fn(O) -> Result<B, E> + 'a, E: ToString {
+    fn parse(&self, input: I) -> ParseResult<I, B> {
+        let (pos, r) = try!(self.p.parse(input));
+
+        match (self.f)(r) {
+            Ok(b) => Ok((pos, b)),
+            Err(e) => Err(ParseError::new(pos.position(), Cow::Owned(e.to_string())))
         }
     }
 }
@@ -171,14 +424,484 @@ struct OrParser<L, R> {
 impl <I, O, L, R> Parser<I, O> for OrParser<L, R>
 where I: Clone, L: Parser<I, O>, R: Parser<I, O> {
     fn parse(&self, input: I) -> ParseResult<I, O> {
-        if let Ok((p, r)) = self.l.parse(input.clone()) {
-            return Ok((p, r))
-        } else if let Ok((p, r)) = self.r.parse(input) {
-            return Ok((p, r))
-        } else { Err(()) }
+        match self.l.parse(input.clone()) {
+            Ok((p, r)) => Ok((p, r)),
+            Err(el) => match self.r.parse(input) {
+                Ok((p, r)) => Ok((p, r)),
+                Err(er) => Err(el.merge(er))
+            }
+        }
+    }
+}
+
+impl <L: Representable, R: Representable> Representable for OrParser<L, R> {
+    fn representation(&self) -> Representation {
+        Representation::Choice(vec![self.l.representation(), self.r.representation()])
+    }
+}
+
+struct Choice<P> {
+    parsers: Vec<P>
+}
+
+fn choice<P>(parsers: Vec<P>) -> Choice<P> {
+    Choice { parsers: parsers }
+}
+
+impl <I: Generator, O, P: Parser<I, O>> Parser<I, O> for Choice<P> {
+    fn parse(&self, input: I) -> ParseResult<I, O> {
+        let mut err: Option<ParseError> = None;
+
+        for p in self.parsers.iter() {
+            match p.parse(input.clone()) {
+                Ok((p, r)) => return Ok((p, r)),
+                Err(e) => {
+                    err = Some(match err {
+                        Some(prev) => prev.merge(e),
+                        None => e
+                    });
+                }
+            }
+        }
+
+        Err(err.unwrap_or_else(|| ParseError::new(input.position(), Cow::Borrowed("choice: no alternatives"))))
+    }
+}
+
+impl <P: Representable> Representable for Choice<P> {
+    fn representation(&self) -> Representation {
+        Representation::Choice(self.parsers.iter().map(|p| p.representation()).collect())
+    }
+}
+
+struct NamedParser<P> {
+    name: String,
+    p: P
+}
+
+fn named<P>(name: &str, p: P) -> NamedParser<P> {
+    NamedParser { name: name.to_string(), p: p }
+}
+
+impl <I, O, P: Parser<I, O>> Parser<I, O> for NamedParser<P> {
+    fn parse(&self, input: I) -> ParseResult<I, O> {
+        self.p.parse(input)
+    }
+}
+
+impl <P> Representable for NamedParser<P> {
+    fn representation(&self) -> Representation {
+        Representation::Nonterminal(self.name.clone())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Assoc {
+    Left,
+    Right
+}
+
+struct PrecedenceParser<A, Op, T, F> {
+    atom: A,
+    op: Op,
+    _output: PhantomData<(T, F)>
+}
+
+fn precedence<A, Op, T, F>(atom: A, op: Op) -> PrecedenceParser<A, Op, T, F> {
+    PrecedenceParser { atom: atom, op: op, _output: PhantomData }
+}
+
+impl <A, Op, T, F> PrecedenceParser<A, Op, T, F> {
+    fn parse_expr<I: Clone>(&self, input: I, min_bp: u32) -> ParseResult<I, T>
+    where A: Parser<I, T>, Op: Parser<I, (u32, Assoc, F)>, F: Fn(T, T) -> T {
+        let (mut pos, mut lhs) = try!(self.atom.parse(input));
+
+        loop {
+            match self.op.parse(pos.clone()) {
+                Ok((after_op, (bp, assoc, fold))) => {
+                    if bp < min_bp {
+                        break;
+                    }
+
+                    let next_bp = match assoc {
+                        Assoc::Left => bp + 1,
+                        Assoc::Right => bp
+                    };
+
+                    let (next_pos, rhs) = try!(self.parse_expr(after_op, next_bp));
+                    lhs = fold(lhs, rhs);
+                    pos = next_pos;
+                }
+                Err(_) => break
+            }
+        }
+
+        Ok((pos, lhs))
+    }
+}
+
+impl <I: Clone, T, F, A, Op> Parser<I, T> for PrecedenceParser<A, Op, T, F>
+where A: Parser<I, T>, Op: Parser<I, (u32, Assoc, F)>, F: Fn(T, T) -> T {
+    fn parse(&self, input: I) -> ParseResult<I, T> {
+        self.parse_expr(input, 0)
+    }
+}
+
+
+#[test] fn test_or_furthest_failure_wins() {
+    let input = StrGenerator::new("ac");
+    let l = ConcatParser { l: 'a', r: 'x' };
+    let r = ConcatParser { l: 'b', r: 'y' };
+    let parser = OrParser { l: l, r: r };
+
+    match parser.parse(input) {
+        Err(e) => {
+            assert_eq!(e.offset, 1);
+            assert_eq!(e.expected, vec![Cow::Owned("'x'".to_string())]);
+        }
+        Ok(_) => panic!("expected failure")
+    }
+}
+
+#[test] fn test_or_merges_expected_on_tie() {
+    let input = StrGenerator::new("z");
+    let parser = OrParser { l: 'a', r: 'b' };
+
+    match parser.parse(input) {
+        Err(e) => {
+            assert_eq!(e.offset, 0);
+            assert_eq!(e.expected.len(), 2);
+        }
+        Ok(_) => panic!("expected failure")
+    }
+}
+
+#[test] fn test_repeat_stops_at_max() {
+    let input = StrGenerator::new("aaaa");
+    let parser = RepeatParser { p: 'a', min: 1, max: Some(2) };
+
+    match parser.parse(input) {
+        Ok((i, r)) => {
+            assert_eq!(r, vec!['a', 'a']);
+            assert_eq!(i.position(), 2);
+        }
+        Err(_) => panic!("expected a successful parse")
+    }
+}
+
+#[test] fn test_repeat_fails_short_of_min() {
+    let input = StrGenerator::new("aa");
+    let parser = RepeatParser { p: 'a', min: 3, max: None };
+
+    match parser.parse(input) {
+        Err(e) => assert_eq!(e.offset, 2),
+        Ok(_) => panic!("expected failure")
+    }
+}
+
+#[test] fn test_repeat_max_below_min_fails_without_panic() {
+    let input = StrGenerator::new("aaaa");
+    let parser = RepeatParser { p: 'a', min: 3, max: Some(1) };
+
+    match parser.parse(input) {
+        Err(e) => assert_eq!(e.offset, 1),
+        Ok(_) => panic!("expected failure")
+    }
+}
+
+#[test] fn test_sep_by_collects_items() {
+    let input = StrGenerator::new("1,2,3");
+    let item = OrParser { l: OrParser { l: '1', r: '2' }, r: '3' };
+    let parser = sep_by(item, ',');
+
+    match parser.parse(input) {
+        Ok((_, r)) => assert_eq!(r, vec!['1', '2', '3']),
+        Err(_) => panic!("expected a successful parse")
+    }
+}
+
+#[test] fn test_sep_by_allows_zero_items() {
+    let input = StrGenerator::new("x");
+    let item = OrParser { l: '1', r: '2' };
+    let parser = sep_by(item, ',');
+
+    match parser.parse(input) {
+        Ok((i, r)) => {
+            assert_eq!(r, vec![]);
+            assert_eq!(i.position(), 0);
+        }
+        Err(_) => panic!("expected a successful parse")
+    }
+}
+
+#[test] fn test_sep_by_stops_before_trailing_sep_without_trailing() {
+    let input = StrGenerator::new("1,2,");
+    let item = OrParser { l: '1', r: '2' };
+    let parser = sep_by(item, ',');
+
+    match parser.parse(input) {
+        Ok((i, r)) => {
+            assert_eq!(r, vec!['1', '2']);
+            assert_eq!(i.position(), 3);
+        }
+        Err(_) => panic!("expected a successful parse")
+    }
+}
+
+#[test] fn test_sep_by_consumes_trailing_sep_when_allowed() {
+    let input = StrGenerator::new("1,2,");
+    let item = OrParser { l: '1', r: '2' };
+    let parser = sep_by(item, ',').allow_trailing(true);
+
+    match parser.parse(input) {
+        Ok((i, r)) => {
+            assert_eq!(r, vec!['1', '2']);
+            assert_eq!(i.position(), 4);
+        }
+        Err(_) => panic!("expected a successful parse")
+    }
+}
+
+#[test] fn test_choice_picks_matching_alternative() {
+    let input = StrGenerator::new("c");
+    let parser = choice(vec!['a', 'b', 'c']);
+
+    match parser.parse(input) {
+        Ok((_, r)) => assert_eq!(r, 'c'),
+        Err(_) => panic!("expected a successful parse")
+    }
+}
+
+#[test] fn test_choice_merges_expected_on_failure() {
+    let input = StrGenerator::new("z");
+    let parser = choice(vec!['a', 'b', 'c']);
+
+    match parser.parse(input) {
+        Err(e) => assert_eq!(e.expected.len(), 3),
+        Ok(_) => panic!("expected failure")
+    }
+}
+
+#[test] fn test_choice_over_empty_vec_fails_instead_of_panicking() {
+    let input = StrGenerator::new("z");
+    let parser = choice::<char>(vec![]);
+
+    match parser.parse(input) {
+        Err(_) => (),
+        Ok(_) => panic!("expected failure")
+    }
+}
+
+#[test] fn test_surrounded_by_discards_delimiters() {
+    let input = StrGenerator::new("(a)");
+    let parser = surrounded_by('(', 'a', ')');
+
+    match parser.parse(input) {
+        Ok((_, r)) => assert_eq!(r, 'a'),
+        Err(_) => panic!("expected a successful parse")
+    }
+}
+
+#[test] fn test_token_parser_matches_slice_element() {
+    let tokens = [1i32, 2, 3];
+    let input = SliceGenerator::new(&tokens);
+    let parser = token(1i32);
+
+    match parser.parse(input) {
+        Ok((i, r)) => {
+            assert_eq!(r, 1);
+            assert_eq!(i.position(), 1);
+        }
+        Err(_) => panic!("expected a successful parse")
+    }
+}
+
+#[test] fn test_token_parser_fails_on_mismatch() {
+    let tokens = [1i32, 2, 3];
+    let input = SliceGenerator::new(&tokens);
+    let parser = token(2i32);
+
+    match parser.parse(input) {
+        Err(e) => assert_eq!(e.offset, 0),
+        Ok(_) => panic!("expected failure")
+    }
+}
+
+#[test] fn test_token_parsers_concat_over_slice_input() {
+    let tokens = [1i32, 2, 3];
+    let input = SliceGenerator::new(&tokens);
+    let parser = ConcatParser { l: token(1i32), r: token(2i32) };
+
+    match parser.parse(input) {
+        Ok((i, (a, b))) => {
+            assert_eq!((a, b), (1, 2));
+            assert_eq!(i.position(), 2);
+        }
+        Err(_) => panic!("expected a successful parse")
+    }
+}
+
+#[test] fn test_slice_matches_leading_subsequence() {
+    let tokens = [1i32, 2, 3, 4];
+    let input = SliceGenerator::new(&tokens);
+    let needle = [1i32, 2, 3];
+    let parser = &needle[..];
+
+    match parser.parse(input) {
+        Ok((i, r)) => {
+            assert_eq!(r, vec![1, 2, 3]);
+            assert_eq!(i.position(), 3);
+        }
+        Err(_) => panic!("expected a successful parse")
+    }
+}
+
+#[test] fn test_slice_fails_on_mismatch() {
+    let tokens = [1i32, 9, 3];
+    let input = SliceGenerator::new(&tokens);
+    let needle = [1i32, 2, 3];
+    let parser = &needle[..];
+
+    match parser.parse(input) {
+        Err(e) => assert_eq!(e.offset, 0),
+        Ok(_) => panic!("expected failure")
+    }
+}
+
+#[test] fn test_slice_fails_when_input_too_short() {
+    let tokens = [1i32];
+    let input = SliceGenerator::new(&tokens);
+    let needle = [1i32, 2, 3];
+    let parser = &needle[..];
+
+    match parser.parse(input) {
+        Err(e) => assert_eq!(e.offset, 0),
+        Ok(_) => panic!("expected failure")
+    }
+}
+
+#[test] fn test_map_res_converts_on_success() {
+    let to_even = |c: char| -> Result<i64, String> {
+        let d = c.to_digit(10).unwrap() as i64;
+        if d % 2 == 0 { Ok(d) } else { Err(format!("{} is odd", d)) }
+    };
+
+    let input = StrGenerator::new("2");
+    let parser = MapResParser { p: '2', f: &to_even };
+
+    match parser.parse(input) {
+        Ok((_, r)) => assert_eq!(r, 2),
+        Err(_) => panic!("expected a successful parse")
+    }
+}
+
+#[test] fn test_map_res_turns_closure_err_into_parse_error() {
+    let to_even = |c: char| -> Result<i64, String> {
+        let d = c.to_digit(10).unwrap() as i64;
+        if d % 2 == 0 { Ok(d) } else { Err(format!("{} is odd", d)) }
+    };
+
+    let input = StrGenerator::new("1");
+    let parser = MapResParser { p: '1', f: &to_even };
+
+    match parser.parse(input) {
+        Err(e) => assert_eq!(e.expected, vec![Cow::Owned("1 is odd".to_string())]),
+        Ok(_) => panic!("expected failure")
+    }
+}
+
+#[test] fn test_map_res_err_lets_or_backtrack_into_next_alternative() {
+    let reject_odd = |c: char| -> Result<i64, String> {
+        let d = c.to_digit(10).unwrap() as i64;
+        if d % 2 == 0 { Ok(d) } else { Err(format!("{} is odd", d)) }
+    };
+    let accept_anything = |c: char| -> Result<i64, String> {
+        Ok(c.to_digit(10).unwrap() as i64 * 100)
+    };
+
+    let input = StrGenerator::new("1");
+    let parser = OrParser {
+        l: MapResParser { p: '1', f: &reject_odd },
+        r: MapResParser { p: '1', f: &accept_anything }
+    };
+
+    match parser.parse(input) {
+        Ok((_, r)) => assert_eq!(r, 100),
+        Err(_) => panic!("expected the second alternative to succeed")
     }
 }
 
+#[test] fn test_precedence_respects_binding_power() {
+    fn add(a: i64, b: i64) -> i64 { a + b }
+    fn mul(a: i64, b: i64) -> i64 { a * b }
+
+    let to_num = |c: char| c.to_digit(10).unwrap() as i64;
+    let to_op = |c: char| -> (u32, Assoc, fn(i64, i64) -> i64) {
+        match c {
+            '+' => (1, Assoc::Left, add),
+            '*' => (2, Assoc::Left, mul),
+            _ => unreachable!()
+        }
+    };
+
+    let digit = Choice { parsers: vec!['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'] };
+    let num = MapParser { p: digit, f: &to_num };
+    let op = MapParser { p: OrParser { l: '+', r: '*' }, f: &to_op };
+    let expr = precedence(num, op);
+
+    let input = StrGenerator::new("1+2*3");
+    match expr.parse(input) {
+        Ok((_, result)) => assert_eq!(result, 7),
+        Err(_) => panic!("expected a successful parse")
+    }
+}
+
+#[test] fn test_precedence_same_precedence_folds_left() {
+    fn sub(a: i64, b: i64) -> i64 { a - b }
+
+    let to_num = |c: char| c.to_digit(10).unwrap() as i64;
+    let to_op = |c: char| -> (u32, Assoc, fn(i64, i64) -> i64) {
+        match c {
+            '-' => (1, Assoc::Left, sub),
+            _ => unreachable!()
+        }
+    };
+
+    let digit = Choice { parsers: vec!['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'] };
+    let num = MapParser { p: digit, f: &to_num };
+    let op = MapParser { p: '-', f: &to_op };
+    let expr = precedence(num, op);
+
+    let input = StrGenerator::new("8-3-2");
+    match expr.parse(input) {
+        Ok((_, result)) => assert_eq!(result, 3),
+        Err(_) => panic!("expected a successful parse")
+    }
+}
+
+#[test] fn test_precedence_right_assoc_op_folds_right() {
+    fn pow(a: i64, b: i64) -> i64 { a.pow(b as u32) }
+
+    let to_num = |c: char| c.to_digit(10).unwrap() as i64;
+    let to_op = |c: char| -> (u32, Assoc, fn(i64, i64) -> i64) {
+        match c {
+            '^' => (1, Assoc::Right, pow),
+            _ => unreachable!()
+        }
+    };
+
+    let digit = Choice { parsers: vec!['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'] };
+    let num = MapParser { p: digit, f: &to_num };
+    let op = MapParser { p: '^', f: &to_op };
+    let expr = precedence(num, op);
+
+    let input = StrGenerator::new("2^3^2");
+    match expr.parse(input) {
+        // right-assoc: 2^(3^2) = 2^9 = 512, not (2^3)^2 = 64
+        Ok((_, result)) => assert_eq!(result, 512),
+        Err(_) => panic!("expected a successful parse")
+    }
+}
 
 #[test] fn test_basic() {
     enum Ops {